@@ -92,11 +92,15 @@
 //!   updates `0..=N`.
 //!
 //! # Webhooks
-//! See the [README FAQ about webhooks](https://github.com/teloxide/teloxide/blob/master/README.md#faq).
+//!
+//! In the webhook way, instead of polling Telegram for updates, you start an
+//! HTTP server and ask Telegram to push updates to it, see [`webhook`].
+//! See also the [README FAQ about webhooks](https://github.com/teloxide/teloxide/blob/master/README.md#faq).
 //!
 //! [`UpdateListener`]: UpdateListener
 //! [`polling_default`]: polling_default
 //! [`polling`]: polling()
+//! [`webhook`]: webhook()
 //! [`Box::get_updates`]: crate::requests::Requester::get_updates
 //! [getting updates]: https://core.telegram.org/bots/api#getting-updates
 //! [long]: https://en.wikipedia.org/wiki/Push_technology#Long_polling
@@ -114,10 +118,12 @@ use crate::{
 
 mod polling;
 mod stateful_listener;
+mod webhook;
 
 pub use self::{
     polling::{polling, polling_default},
     stateful_listener::StatefulListener,
+    webhook::webhook,
 };
 
 /// An update listener.
@@ -125,8 +131,8 @@ pub use self::{
 /// Implementors of this trait allow getting updates from Telegram.
 ///
 /// Currently Telegram has 2 ways of getting updates -- [polling] and
-/// [webhooks]. Currently, only the former one is implemented (see [`polling()`]
-/// and [`polling_default`])
+/// [webhooks]. Both are implemented: see [`polling()`]/[`polling_default`] for
+/// the former and [`webhook()`] for the latter.
 ///
 /// Some functions of this trait are located in the supertrait
 /// ([`AsUpdateStream`]), see also:
@@ -0,0 +1,457 @@
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+
+use futures::{stream, Stream};
+use tokio::{sync::Notify, time::sleep};
+
+use crate::{
+    dispatching::{
+        stop_token::StopToken,
+        update_listeners::{AsUpdateStream, StatefulListener, UpdateListener},
+    },
+    requests::Requester,
+    types::{AllowedUpdate, Update},
+};
+
+/// The default delay before the first retry after a failed `get_updates`
+/// call (see [`PollingBuilder::error_delay`]).
+const DEFAULT_ERROR_DELAY: Duration = Duration::from_millis(500);
+
+/// The default cap on the retry delay (see
+/// [`PollingBuilder::max_error_delay`]).
+const DEFAULT_MAX_ERROR_DELAY: Duration = Duration::from_secs(30);
+
+/// Returns a long polling update listener with the default configuration.
+///
+/// This uses a timeout of 10 seconds and otherwise behaves exactly as
+/// [`polling`] with no further configuration applied.
+///
+/// See also: [`polling`](polling()).
+pub async fn polling_default<R>(requester: R) -> impl UpdateListener<R::Err>
+where
+    R: Requester + Send + 'static,
+{
+    let timeout = Duration::from_secs(10);
+    polling(requester, Some(timeout)).build()
+}
+
+/// Returns a builder for a long/short polling update listener.
+///
+/// - `requester`: Using this requester, the returned update listener will
+///   receive updates.
+/// - `timeout`: A timeout for polling, i.e. [`GetUpdates::timeout`]. Use
+///   `None` for short polling (only suitable for testing).
+///
+/// Call [`.build()`] on the returned builder to obtain the update listener,
+/// or use one of the builder methods to configure it first.
+///
+/// [`GetUpdates::timeout`]: crate::payloads::GetUpdates::timeout
+/// [`.build()`]: PollingBuilder::build
+pub fn polling<R>(requester: R, timeout: Option<Duration>) -> PollingBuilder<R>
+where
+    R: Requester + Send + 'static,
+{
+    PollingBuilder {
+        requester,
+        timeout,
+        limit: None,
+        error_delay: DEFAULT_ERROR_DELAY,
+        max_error_delay: DEFAULT_MAX_ERROR_DELAY,
+        retries: None,
+        drop_pending_updates: false,
+    }
+}
+
+/// A builder for a long/short polling [`UpdateListener`].
+///
+/// Created by [`polling`](polling()).
+#[must_use]
+pub struct PollingBuilder<R> {
+    requester: R,
+    timeout: Option<Duration>,
+    limit: Option<u8>,
+    error_delay: Duration,
+    max_error_delay: Duration,
+    retries: Option<usize>,
+    drop_pending_updates: bool,
+}
+
+impl<R> PollingBuilder<R>
+where
+    R: Requester + Send + 'static,
+{
+    /// Sets a limit of how many updates to fetch per `get_updates` call, i.e.
+    /// [`GetUpdates::limit`].
+    ///
+    /// The fetched batch is buffered internally and drained one update at a
+    /// time, so this only affects how many updates are requested over the
+    /// network at once, not how they are produced from the listener.
+    ///
+    /// Must be in the range `1..=100`; values outside of it are clamped.
+    ///
+    /// Defaults to `None`, i.e. the default imposed by the Bot API (100).
+    ///
+    /// [`GetUpdates::limit`]: crate::payloads::GetUpdates::limit
+    pub fn limit(mut self, limit: u8) -> Self {
+        self.limit = Some(limit.clamp(1, 100));
+        self
+    }
+
+    /// Sets the delay before the first retry of a failed `get_updates` call.
+    ///
+    /// After each further consecutive failure this delay is doubled, up to
+    /// [`max_error_delay`], and it is reset back to this value as soon as a
+    /// `get_updates` call succeeds. If this is set above [`max_error_delay`]
+    /// (regardless of which setter is called first), it is clamped down to it
+    /// so that no delay ever exceeds the configured cap.
+    ///
+    /// Defaults to 500ms.
+    ///
+    /// [`max_error_delay`]: PollingBuilder::max_error_delay
+    pub fn error_delay(mut self, delay: Duration) -> Self {
+        self.error_delay = delay;
+        self
+    }
+
+    /// Sets the cap on the delay between retries (see [`error_delay`]).
+    ///
+    /// Defaults to 30 seconds.
+    ///
+    /// [`error_delay`]: PollingBuilder::error_delay
+    pub fn max_error_delay(mut self, delay: Duration) -> Self {
+        self.max_error_delay = delay;
+        self
+    }
+
+    /// Sets how many consecutive `get_updates` failures are retried before
+    /// giving up and yielding the error through the update stream.
+    ///
+    /// Defaults to `None`, meaning the listener retries forever and never
+    /// surfaces a `get_updates` error.
+    pub fn retries(mut self, retries: usize) -> Self {
+        self.retries = Some(retries);
+        self
+    }
+
+    /// If enabled, the listener discards the backlog of updates accumulated
+    /// while the bot was offline before starting to poll.
+    ///
+    /// This is implemented as a single `get_updates` call with `offset = -1`
+    /// issued before the first real poll, which confirms (and thus drops)
+    /// every update Telegram had queued up. If that call fails, it is
+    /// handled like a failed poll (backoff and [`retries`]) and retried, and
+    /// no real poll is made until it succeeds, so a transient error on
+    /// startup can't silently turn this into a no-op.
+    ///
+    /// [`retries`]: PollingBuilder::retries
+    ///
+    /// Defaults to `false`.
+    pub fn drop_pending_updates(mut self, drop_pending_updates: bool) -> Self {
+        self.drop_pending_updates = drop_pending_updates;
+        self
+    }
+
+    /// Builds the long/short polling update listener.
+    pub fn build(self) -> impl UpdateListener<R::Err> {
+        let Self {
+            requester,
+            timeout,
+            limit,
+            error_delay,
+            max_error_delay,
+            retries,
+            drop_pending_updates,
+        } = self;
+
+        let state = State {
+            requester,
+            timeout: timeout.map(|t| t.as_secs() as u32),
+            limit,
+            allowed_updates: None,
+            offset: 0,
+            buffer: VecDeque::new(),
+            error_delay,
+            max_error_delay,
+            // `error_delay` isn't guaranteed to be `<= max_error_delay` (the
+            // builder methods can be called in any order), so clamp here to
+            // make sure the very first retry already honours the cap.
+            current_delay: error_delay.min(max_error_delay),
+            consecutive_failures: 0,
+            retries,
+            gave_up: false,
+            needs_drop_pending_updates: drop_pending_updates,
+            stop_signal: Arc::new(StopSignal::default()),
+        };
+
+        StatefulListener::new_with_hints(
+            state,
+            stream,
+            |state: &mut State<R>| PollingStopToken(Arc::clone(&state.stop_signal)),
+            |state: &mut State<R>, hint| state.allowed_updates = Some(hint.collect()),
+        )
+    }
+}
+
+struct State<B> {
+    requester: B,
+    timeout: Option<u32>,
+    limit: Option<u8>,
+    allowed_updates: Option<Vec<AllowedUpdate>>,
+    offset: i32,
+    /// Updates already fetched from Telegram, but not yet handed to the
+    /// consumer of the stream.
+    buffer: VecDeque<Update>,
+    error_delay: Duration,
+    max_error_delay: Duration,
+    current_delay: Duration,
+    consecutive_failures: usize,
+    retries: Option<usize>,
+    /// Set once the retry budget has been exhausted, so the stream doesn't
+    /// resume hammering `get_updates` after it has already given up.
+    gave_up: bool,
+    /// Set until the one-time backlog-dropping call has been made.
+    needs_drop_pending_updates: bool,
+    stop_signal: Arc<StopSignal>,
+}
+
+fn stream<B>(st: &mut State<B>) -> impl Stream<Item = Result<Update, B::Err>> + Send + '_
+where
+    B: Requester + Send,
+{
+    stream::unfold(st, move |state| async move {
+        // Drain the buffer before issuing another network request.
+        if let Some(update) = state.buffer.pop_front() {
+            return Some((Ok(update), state));
+        }
+
+        if state.gave_up || state.stop_signal.is_stopped() {
+            return None;
+        }
+
+        loop {
+            // Until the backlog has been dropped, that call takes the place of
+            // a real poll, with the same backoff and retry budget on failure.
+            let res = if state.needs_drop_pending_updates {
+                match drop_pending_updates(state).await {
+                    Ok(()) => {
+                        state.needs_drop_pending_updates = false;
+                        Ok(Vec::new())
+                    }
+                    Err(err) => Err(err),
+                }
+            } else {
+                let mut req = state.requester.get_updates();
+                req.offset = Some(state.offset);
+                req.timeout = state.timeout;
+                req.limit = state.limit;
+                // `allowed_updates` only needs to be sent once for Telegram to
+                // remember it, so it's taken rather than cloned.
+                req.allowed_updates = state.allowed_updates.take();
+                req.send().await
+            };
+
+            match res {
+                Ok(updates) => {
+                    // A successful poll resets the backoff (clamped for the
+                    // same reason as in `build`, above).
+                    state.current_delay = state.error_delay.min(state.max_error_delay);
+                    state.consecutive_failures = 0;
+
+                    if let Some(upd) = updates.last() {
+                        state.offset = upd.id + 1;
+                    }
+
+                    break match refill(&mut state.buffer, updates) {
+                        Some(update) => Some((Ok(update), state)),
+                        // An empty batch; poll again immediately.
+                        None => continue,
+                    };
+                }
+                Err(err) => {
+                    state.consecutive_failures += 1;
+
+                    log::error!(
+                        "get_updates failed, retrying in {:?}: {}",
+                        state.current_delay,
+                        err
+                    );
+
+                    // The delay is applied unconditionally, even if we are
+                    // about to give up, so a sustained outage never turns
+                    // into a zero-delay busy loop against Telegram. Waiting
+                    // also races the stop signal, so a `stop()` called
+                    // mid-backoff is honored right away instead of only
+                    // after the next successful (or exhausted) poll.
+                    tokio::select! {
+                        _ = sleep(state.current_delay) => {}
+                        _ = state.stop_signal.wait() => break None,
+                    }
+                    state.current_delay = double_capped(state.current_delay, state.max_error_delay);
+
+                    if retries_exhausted(state.consecutive_failures, state.retries) {
+                        // Give up for good: yield the error once, then stop
+                        // polling instead of repeating this forever.
+                        state.gave_up = true;
+                        break Some((Err(err), state));
+                    }
+                }
+            }
+        }
+    })
+}
+
+/// Doubles `delay`, capped at `max`.
+fn double_capped(delay: Duration, max: Duration) -> Duration {
+    (delay * 2).min(max)
+}
+
+/// Returns `true` once `consecutive_failures` has exceeded the configured
+/// `retries` budget (`None` means "retry forever").
+fn retries_exhausted(consecutive_failures: usize, retries: Option<usize>) -> bool {
+    matches!(retries, Some(retries) if consecutive_failures > retries)
+}
+
+/// Appends `batch` to `buffer` and pops the first update off of it, if any.
+///
+/// This keeps update ordering: the buffer is drained before a newly fetched
+/// batch is appended to it.
+fn refill<T>(buffer: &mut VecDeque<T>, batch: impl IntoIterator<Item = T>) -> Option<T> {
+    buffer.extend(batch);
+    buffer.pop_front()
+}
+
+/// Advances `state.offset` past every update currently queued by Telegram,
+/// without yielding any of them, by asking for only the very last update and
+/// confirming everything up to it.
+///
+/// This deliberately ignores `state.allowed_updates`/`state.limit` — it's not
+/// a real poll, just a way to fast-forward the offset.
+async fn drop_pending_updates<B>(state: &mut State<B>) -> Result<(), B::Err>
+where
+    B: Requester + Send,
+{
+    let mut req = state.requester.get_updates();
+    req.offset = Some(-1);
+    req.limit = Some(1);
+    req.timeout = Some(0);
+
+    match req.send().await {
+        Ok(updates) => {
+            if let Some(upd) = updates.last() {
+                state.offset = upd.id + 1;
+            }
+            Ok(())
+        }
+        Err(err) => {
+            log::error!("failed to drop pending updates: {}", err);
+            Err(err)
+        }
+    }
+}
+
+/// A shutdown flag paired with a [`Notify`] so waiters sleeping inside the
+/// retry loop observe a [`stop`](StopSignal::stop) without having to poll.
+#[derive(Default)]
+struct StopSignal {
+    stopped: AtomicBool,
+    notify: Notify,
+}
+
+impl StopSignal {
+    fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::Relaxed)
+    }
+
+    fn stop(&self) {
+        self.stopped.store(true, Ordering::Relaxed);
+        self.notify.notify_one();
+    }
+
+    /// Resolves once [`stop`](StopSignal::stop) has been called.
+    async fn wait(&self) {
+        while !self.is_stopped() {
+            self.notify.notified().await;
+        }
+    }
+}
+
+/// A [`StopToken`] returned by the polling [`UpdateListener`].
+#[derive(Clone)]
+struct PollingStopToken(Arc<StopSignal>);
+
+impl StopToken for PollingStopToken {
+    fn stop(self) {
+        self.0.stop();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn double_capped_doubles_until_the_cap() {
+        let max = Duration::from_secs(30);
+
+        let mut delay = Duration::from_millis(500);
+        for expected_ms in [1_000, 2_000, 4_000, 8_000, 16_000] {
+            delay = double_capped(delay, max);
+            assert_eq!(delay, Duration::from_millis(expected_ms));
+        }
+
+        // Doubling past the cap saturates instead of overflowing it.
+        delay = double_capped(delay, max);
+        assert_eq!(delay, max);
+        delay = double_capped(delay, max);
+        assert_eq!(delay, max);
+    }
+
+    #[test]
+    fn retries_exhausted_never_gives_up_with_no_limit() {
+        assert!(!retries_exhausted(0, None));
+        assert!(!retries_exhausted(1_000_000, None));
+    }
+
+    #[test]
+    fn retries_exhausted_respects_the_configured_budget() {
+        assert!(!retries_exhausted(0, Some(3)));
+        assert!(!retries_exhausted(3, Some(3)));
+        assert!(retries_exhausted(4, Some(3)));
+    }
+
+    #[test]
+    fn initial_delay_never_exceeds_the_configured_max_even_if_set_backwards() {
+        let error_delay = Duration::from_secs(60);
+        let max_error_delay = Duration::from_secs(30);
+
+        // Mirrors the clamp done in `build` and on every successful poll:
+        // an `error_delay` above `max_error_delay` must never win out.
+        assert_eq!(error_delay.min(max_error_delay), max_error_delay);
+    }
+
+    #[test]
+    fn refill_drains_the_existing_buffer_before_the_new_batch() {
+        let mut buffer = VecDeque::from([1, 2]);
+
+        // The leftover `1` from a previous batch must come out before
+        // anything from the batch just appended.
+        assert_eq!(refill(&mut buffer, [3, 4]), Some(1));
+        assert_eq!(buffer.pop_front(), Some(2));
+        assert_eq!(buffer.pop_front(), Some(3));
+        assert_eq!(buffer.pop_front(), Some(4));
+        assert_eq!(buffer.pop_front(), None);
+    }
+
+    #[test]
+    fn refill_on_an_empty_batch_leaves_the_buffer_empty() {
+        let mut buffer: VecDeque<i32> = VecDeque::new();
+        assert_eq!(refill(&mut buffer, []), None);
+        assert!(buffer.is_empty());
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{
+    dispatching::{
+        stop_token::StopToken,
+        update_listeners::{AsUpdateStream, UpdateListener},
+    },
+    types::AllowedUpdate,
+};
+
+/// A listener created from the parts.
+///
+/// This type allows to create an [`UpdateListener`] from a state and 2-3
+/// functions, without having to name the resulting type.
+///
+///  - `state`: The state of the listener (e.g.: a bot, the current offset).
+///  - `stream`: A function that turns `&mut state` into a stream of updates.
+///  - `stop_token`: A function that returns a [`StopToken`] for this
+///    listener.
+///  - `hint_allowed_updates`: An optional function used as
+///    [`UpdateListener::hint_allowed_updates`].
+///
+/// [`StopToken`]: crate::dispatching::stop_token::StopToken
+#[non_exhaustive]
+pub struct StatefulListener<St, Assf, ThF> {
+    /// The state of the listener.
+    pub state: St,
+
+    /// The function used as [`AsUpdateStream::as_stream`].
+    pub stream: Assf,
+
+    /// The function used as [`UpdateListener::stop_token`].
+    pub stop_token: ThF,
+
+    /// The function used as [`UpdateListener::hint_allowed_updates`].
+    ///
+    /// If left as `None`, the default (no-op) implementation is used.
+    pub hint_allowed_updates:
+        Option<fn(state: &mut St, hint: &mut dyn Iterator<Item = AllowedUpdate>)>,
+}
+
+impl<St, Assf, ThF> StatefulListener<St, Assf, ThF> {
+    /// Creates a new `StatefulListener` from its parts, without a
+    /// `hint_allowed_updates` implementation.
+    pub fn new(state: St, stream: Assf, stop_token: ThF) -> Self {
+        Self { state, stream, stop_token, hint_allowed_updates: None }
+    }
+
+    /// Creates a new `StatefulListener` from its parts, including a
+    /// `hint_allowed_updates` implementation.
+    pub fn new_with_hints(
+        state: St,
+        stream: Assf,
+        stop_token: ThF,
+        hint_allowed_updates: fn(&mut St, &mut dyn Iterator<Item = AllowedUpdate>),
+    ) -> Self {
+        Self { state, stream, stop_token, hint_allowed_updates: Some(hint_allowed_updates) }
+    }
+}
+
+impl<'a, St, Assf, ThF, Strm, E> AsUpdateStream<'a, E> for StatefulListener<St, Assf, ThF>
+where
+    St: 'a,
+    Assf: FnMut(&'a mut St) -> Strm,
+    Strm: futures::Stream<Item = Result<crate::types::Update, E>> + Send + 'a,
+{
+    type Stream = Strm;
+
+    fn as_stream(&'a mut self) -> Self::Stream {
+        (self.stream)(&mut self.state)
+    }
+}
+
+impl<St, Assf, ThF, Tok, E> UpdateListener<E> for StatefulListener<St, Assf, ThF>
+where
+    Self: for<'a> AsUpdateStream<'a, E>,
+    ThF: FnMut(&mut St) -> Tok,
+    Tok: StopToken,
+{
+    type StopToken = Tok;
+
+    fn stop_token(&mut self) -> Self::StopToken {
+        (self.stop_token)(&mut self.state)
+    }
+
+    fn hint_allowed_updates(&mut self, hint: &mut dyn Iterator<Item = AllowedUpdate>) {
+        if let Some(f) = self.hint_allowed_updates {
+            f(&mut self.state, hint)
+        }
+    }
+}
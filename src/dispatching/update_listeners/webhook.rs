@@ -0,0 +1,244 @@
+use std::{net::SocketAddr, sync::Arc};
+
+use hyper::{
+    body::HttpBody,
+    service::{make_service_fn, service_fn},
+    Body, Method, Response, Server,
+};
+use reqwest::Url;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio_stream::wrappers::ReceiverStream;
+
+use crate::{
+    dispatching::{
+        stop_token::StopToken,
+        update_listeners::{AsUpdateStream, StatefulListener, UpdateListener},
+    },
+    requests::Requester,
+    types::{AllowedUpdate, Update},
+};
+
+/// How many updates may be buffered between the HTTP server and
+/// [`AsUpdateStream::as_stream`] before the server starts backpressuring.
+const CHANNEL_CAPACITY: usize = 100;
+
+/// The largest request body accepted from a single webhook request.
+///
+/// `addr` is a plain, unauthenticated HTTP socket (see [`webhook`]), so
+/// anyone who can reach it — not just Telegram — could otherwise
+/// force unbounded memory growth by POSTing an oversized body.
+const MAX_BODY_SIZE: u64 = 1024 * 1024;
+
+/// Starts a webhook-based [`UpdateListener`].
+///
+/// This spawns a lightweight HTTP server bound to `addr` that accepts
+/// Telegram's POSTed `Update` bodies on `path` (taken from `url`) and decodes
+/// them straight into `Result<Update, serde_json::Error>`, forwarding them
+/// through a bounded internal channel. A slow consumer of the returned
+/// listener's stream backpressures the server, since once the channel is full
+/// each further request is left unanswered until there is room in it.
+///
+/// `addr` is only the local address the HTTP server binds to; it is plain
+/// HTTP and terminates no TLS. `url` is the public HTTPS address at which
+/// Telegram can actually reach that server — in a real deployment this is the
+/// address of a reverse proxy that terminates TLS and forwards to `addr`, so
+/// the two are generally *not* the same. `url`'s path is used to route
+/// incoming requests, and `url` itself is what gets sent to Telegram via
+/// [`hint_allowed_updates`]'s `setWebhook` call.
+///
+/// `bot` is only used to push [`hint_allowed_updates`] calls to Telegram
+/// through `setWebhook`; it plays no role in serving the updates themselves.
+/// Pointing Telegram at `url` (i.e. the initial `setWebhook` call) is the
+/// caller's responsibility.
+///
+/// # Errors
+///
+/// Returns `Err` if `addr` can't be bound, e.g. because the port is already
+/// in use.
+///
+/// [`hint_allowed_updates`]: UpdateListener::hint_allowed_updates
+pub fn webhook<R>(
+    bot: R,
+    addr: SocketAddr,
+    url: Url,
+) -> Result<impl UpdateListener<serde_json::Error>, hyper::Error>
+where
+    R: Requester + Clone + Send + Sync + 'static,
+{
+    let path = url.path().to_owned();
+    let (tx, rx) = mpsc::channel(CHANNEL_CAPACITY);
+    let (stop_tx, stop_rx) = oneshot::channel();
+
+    let make_svc = make_service_fn(move |_conn| {
+        let tx = tx.clone();
+        let path = path.clone();
+
+        async move {
+            Ok::<_, std::convert::Infallible>(service_fn(move |req| {
+                let tx = tx.clone();
+                let path = path.clone();
+
+                async move {
+                    if !should_handle(req.method(), req.uri().path(), &path) {
+                        return Ok::<_, std::convert::Infallible>(
+                            Response::builder().status(404).body(Body::empty()).unwrap(),
+                        );
+                    }
+
+                    let bytes = match read_body_limited(req.into_body(), MAX_BODY_SIZE).await {
+                        Ok(bytes) => bytes,
+                        Err(BodyReadError::TooLarge) => {
+                            return Ok(Response::builder().status(413).body(Body::empty()).unwrap())
+                        }
+                        Err(BodyReadError::Read) => {
+                            return Ok(Response::builder().status(400).body(Body::empty()).unwrap())
+                        }
+                    };
+
+                    let update = serde_json::from_slice::<Update>(&bytes);
+
+                    // Backpressure: if the channel is full, this await blocks
+                    // the request until `as_stream`'s consumer catches up.
+                    let _ = tx.send(update).await;
+
+                    Ok(Response::new(Body::empty()))
+                }
+            }))
+        }
+    });
+
+    let server = Server::try_bind(&addr)?.serve(make_svc);
+    let server = server.with_graceful_shutdown(async {
+        let _ = stop_rx.await;
+    });
+
+    tokio::spawn(async move {
+        if let Err(err) = server.await {
+            log::error!("webhook server error: {}", err);
+        }
+    });
+
+    let state = State { bot, url, rx: Some(rx), stop_tx: Arc::new(Mutex::new(Some(stop_tx))) };
+
+    Ok(StatefulListener::new_with_hints(
+        state,
+        |state: &mut State<R>| {
+            ReceiverStream::new(state.rx.take().expect("`as_stream` called more than once"))
+        },
+        |state: &mut State<R>| WebhookStopToken(Arc::clone(&state.stop_tx)),
+        |state: &mut State<R>, hint: &mut dyn Iterator<Item = AllowedUpdate>| {
+            let bot = state.bot.clone();
+            let url = state.url.clone();
+            let allowed_updates = hint.collect::<Vec<_>>();
+
+            // `hint_allowed_updates` is sync, so the `setWebhook` call that
+            // actually registers the new allowed updates with Telegram is
+            // fired off in the background.
+            tokio::spawn(async move {
+                let res = bot.set_webhook(url).allowed_updates(allowed_updates).send().await;
+                if let Err(err) = res {
+                    log::error!("failed to update allowed_updates via setWebhook: {}", err);
+                }
+            });
+        },
+    ))
+}
+
+/// Whether an incoming request should be treated as a webhook update, i.e. a
+/// `POST` to exactly the path Telegram was told to push updates to.
+fn should_handle(method: &Method, req_path: &str, configured_path: &str) -> bool {
+    method == Method::POST && req_path == configured_path
+}
+
+/// Why [`read_body_limited`] failed to produce a body.
+enum BodyReadError {
+    /// The body exceeded `limit` before it was fully read.
+    TooLarge,
+    /// The underlying connection failed while reading the body.
+    Read,
+}
+
+/// Reads `body` into memory, stopping with [`BodyReadError::TooLarge`] as
+/// soon as more than `limit` bytes have been read.
+///
+/// Unlike `hyper::body::to_bytes`, this never buffers more than `limit` bytes
+/// regardless of what (if anything) the `Content-Length` header claims.
+async fn read_body_limited(mut body: Body, limit: u64) -> Result<Vec<u8>, BodyReadError> {
+    let mut bytes = Vec::new();
+
+    while let Some(chunk) = body.data().await {
+        let chunk = chunk.map_err(|_| BodyReadError::Read)?;
+
+        if bytes.len() as u64 + chunk.len() as u64 > limit {
+            return Err(BodyReadError::TooLarge);
+        }
+
+        bytes.extend_from_slice(&chunk);
+    }
+
+    Ok(bytes)
+}
+
+struct State<B> {
+    bot: B,
+    url: Url,
+    rx: Option<mpsc::Receiver<Result<Update, serde_json::Error>>>,
+    stop_tx: Arc<Mutex<Option<oneshot::Sender<()>>>>,
+}
+
+/// A [`StopToken`] that shuts the webhook server down gracefully.
+///
+/// Triggering the server's graceful shutdown makes it stop accepting new
+/// connections;
+/// in-flight requests still get a chance to push their update into the
+/// channel, and [`AsUpdateStream::as_stream`] keeps draining that channel
+/// until it's empty and closed.
+struct WebhookStopToken(Arc<Mutex<Option<oneshot::Sender<()>>>>);
+
+impl StopToken for WebhookStopToken {
+    fn stop(self) {
+        tokio::spawn(async move {
+            if let Some(stop_tx) = self.0.lock().await.take() {
+                let _ = stop_tx.send(());
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn should_handle_accepts_only_a_post_to_the_configured_path() {
+        assert!(should_handle(&Method::POST, "/webhook", "/webhook"));
+    }
+
+    #[test]
+    fn should_handle_rejects_other_methods() {
+        assert!(!should_handle(&Method::GET, "/webhook", "/webhook"));
+        assert!(!should_handle(&Method::PUT, "/webhook", "/webhook"));
+    }
+
+    #[test]
+    fn should_handle_rejects_other_paths() {
+        assert!(!should_handle(&Method::POST, "/other", "/webhook"));
+    }
+
+    #[test]
+    fn malformed_body_fails_to_decode_as_an_update() {
+        assert!(serde_json::from_slice::<Update>(b"not json").is_err());
+    }
+
+    #[tokio::test]
+    async fn read_body_limited_accepts_a_body_within_the_limit() {
+        let bytes = read_body_limited(Body::from(&b"hello"[..]), 5).await.unwrap();
+        assert_eq!(bytes, b"hello");
+    }
+
+    #[tokio::test]
+    async fn read_body_limited_rejects_a_body_over_the_limit() {
+        let err = read_body_limited(Body::from(&b"hello"[..]), 4).await.unwrap_err();
+        assert!(matches!(err, BodyReadError::TooLarge));
+    }
+}